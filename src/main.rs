@@ -1,24 +1,180 @@
 use std::cmp;
-use std::sync::{mpsc, Arc, Mutex};
+use std::cmp::{Ordering, Reverse};
+use std::collections::{BinaryHeap, HashMap};
+use std::error::Error;
+use std::fmt;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, Ordering as AtomicOrdering};
+use std::sync::{mpsc, Arc, Condvar, Mutex};
 use std::thread;
-use std::time::{SystemTime, UNIX_EPOCH, Duration};
-use rand::Rng;
-use log::info;
+use std::time::{Instant, SystemTime, UNIX_EPOCH, Duration};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use log::{info, warn};
 use env_logger;
 
+/// Maximum allowed skew, in milliseconds, between a remote timestamp's
+/// physical component and our own notion of "now" before it is rejected.
+const DEFAULT_MAX_DRIFT_MILLIS: u64 = 500;
+
+/// Bits of the packed 64-bit clock representation given to the logical
+/// counter; the remaining high bits hold milliseconds since the epoch.
+const LOGICAL_BITS: u32 = 16;
+const LOGICAL_MASK: u64 = (1 << LOGICAL_BITS) - 1;
+const MAX_LOGICAL: u64 = LOGICAL_MASK;
+
+/// Sub-buckets per power-of-two range in a `Histogram`; higher values give
+/// finer (more "significant digits" of) relative resolution at the cost of
+/// more distinct buckets.
+const HIST_SUB_BUCKETS_PER_OCTAVE: u32 = 32;
+
+/// How often, in seconds, a node's metrics logger appends an interval-log
+/// line to disk.
+const METRICS_LOG_INTERVAL_SECS: u64 = 10;
+
+/// Simulation tick rate used to convert a node's byte-per-second capacity
+/// into a per-step transmission budget.
+const STEPS_PER_SECOND: u64 = 1000;
+
+/// Identifies the node that produced a `Timestamp`, used as the final
+/// tie-breaker so that two timestamps are never equal across nodes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+struct NodeId(u32);
+
+/// A globally unique, totally ordered HLC timestamp.
+///
+/// Ordering compares `(physical, logical, id)` lexicographically: physical
+/// time dominates, the logical counter breaks ties within the same
+/// millisecond, and `id` breaks any remaining tie so that timestamps from
+/// distinct nodes are never equal.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct Timestamp {
+    physical: u64,
+    logical: u64,
+    id: NodeId,
+}
+
+impl Ord for Timestamp {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (self.physical, self.logical, self.id).cmp(&(other.physical, other.logical, other.id))
+    }
+}
+
+impl PartialOrd for Timestamp {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Timestamp {
+    /// Pack `(physical, logical)` into a single `u64` for compact wire
+    /// transmission. `id` is dropped: it travels alongside the packed value
+    /// instead (e.g. as `Message::sender_id`), since the receiver already
+    /// knows which peer a message came from.
+    fn to_packed(self) -> PackedClock {
+        PackedClock((self.physical << LOGICAL_BITS) | (self.logical & LOGICAL_MASK))
+    }
+
+    /// Reconstruct a `Timestamp` from a packed value and the `id` of the
+    /// node that produced it.
+    fn from_packed(packed: PackedClock, id: NodeId) -> Self {
+        Timestamp {
+            physical: packed.0 >> LOGICAL_BITS,
+            logical: packed.0 & LOGICAL_MASK,
+            id,
+        }
+    }
+}
+
+/// A `(physical, logical)` pair packed into a single `u64`: the high bits
+/// are milliseconds since the UNIX epoch, the low `LOGICAL_BITS` bits are
+/// the logical counter. This is what actually crosses the wire in a
+/// `Message`, shrinking the footprint versus sending both fields.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct PackedClock(u64);
+
+impl PackedClock {
+    fn as_u64(&self) -> u64 {
+        self.0
+    }
+
+    fn from_u64(value: u64) -> Self {
+        PackedClock(value)
+    }
+}
+
+impl fmt::Display for PackedClock {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// Error returned when parsing a `PackedClock` from a string fails.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct ParsePackedClockError;
+
+impl fmt::Display for ParsePackedClockError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid packed clock value")
+    }
+}
+
+impl Error for ParsePackedClockError {}
+
+impl FromStr for PackedClock {
+    type Err = ParsePackedClockError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.parse::<u64>().map(PackedClock).map_err(|_| ParsePackedClockError)
+    }
+}
+
+/// Errors produced while updating a `HybridLogicalClock`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum ClockError {
+    /// The remote timestamp's physical time exceeds `max(now, self.physical_time)`
+    /// by more than the clock's configured `max_drift`, so it was rejected.
+    DriftTooLarge,
+}
+
+impl fmt::Display for ClockError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ClockError::DriftTooLarge => write!(f, "remote timestamp drifts too far ahead of the local clock"),
+        }
+    }
+}
+
+impl Error for ClockError {}
+
 /// Hybrid Logical Clock (HLC) struct
 #[derive(Clone, Debug)]
 struct HybridLogicalClock {
+    id: NodeId,
     physical_time: u64,
     logical_counter: u64,
+    max_drift_millis: u64,
 }
 
 impl HybridLogicalClock {
-    /// Create a new HLC initialized to the current time
-    fn new() -> Self {
+    /// Create a new HLC initialized to the current time, using the default
+    /// drift bound.
+    fn new(id: NodeId) -> Self {
         HybridLogicalClock {
+            id,
             physical_time: current_millis(),
             logical_counter: 0,
+            max_drift_millis: DEFAULT_MAX_DRIFT_MILLIS,
+        }
+    }
+
+    /// Create a new HLC with an explicit drift bound, in milliseconds.
+    fn with_max_drift(id: NodeId, max_drift_millis: u64) -> Self {
+        HybridLogicalClock {
+            max_drift_millis,
+            ..HybridLogicalClock::new(id)
         }
     }
 
@@ -26,28 +182,73 @@ impl HybridLogicalClock {
     fn increment(&mut self) {
         let now = current_millis();
         if now == self.physical_time {
-            self.logical_counter += 1;
+            if self.logical_counter >= MAX_LOGICAL {
+                // The logical counter has exhausted its bit width for this
+                // millisecond; spin until physical time ticks forward
+                // rather than silently wrapping.
+                self.physical_time = Self::spin_until_after(self.physical_time);
+                self.logical_counter = 0;
+            } else {
+                self.logical_counter += 1;
+            }
         } else {
             self.physical_time = now;
             self.logical_counter = 0;
         }
     }
 
-    /// Update the clock on receiving a message
-    fn update(&mut self, remote: &HybridLogicalClock) {
+    /// Busy-wait until `current_millis()` has advanced past `baseline`.
+    fn spin_until_after(baseline: u64) -> u64 {
+        loop {
+            let now = current_millis();
+            if now > baseline {
+                return now;
+            }
+            thread::yield_now();
+        }
+    }
+
+    /// Update the clock on receiving a remote timestamp.
+    ///
+    /// If `remote.physical` exceeds `max(now, self.physical_time)` by more
+    /// than `max_drift_millis`, the remote timestamp is rejected with
+    /// `ClockError::DriftTooLarge` and the local clock is left untouched,
+    /// so a single wildly-skewed peer cannot drag the whole cluster forward.
+    fn update_with_timestamp(&mut self, remote: &Timestamp) -> Result<(), ClockError> {
         let now = current_millis();
-        self.physical_time = cmp::max(now, remote.physical_time);
+        let ceiling = cmp::max(now, self.physical_time);
+        if remote.physical > ceiling + self.max_drift_millis {
+            return Err(ClockError::DriftTooLarge);
+        }
 
-        if self.physical_time == remote.physical_time {
-            self.logical_counter = cmp::max(self.logical_counter, remote.logical_counter) + 1;
+        let mut new_physical = cmp::max(ceiling, remote.physical);
+        let mut new_logical = if new_physical == self.physical_time && new_physical == remote.physical {
+            cmp::max(self.logical_counter, remote.logical) + 1
+        } else if new_physical == self.physical_time {
+            self.logical_counter + 1
+        } else if new_physical == remote.physical {
+            remote.logical + 1
         } else {
-            self.logical_counter = 0;
+            0
+        };
+
+        if new_logical > MAX_LOGICAL {
+            new_physical = Self::spin_until_after(new_physical);
+            new_logical = 0;
         }
+
+        self.physical_time = new_physical;
+        self.logical_counter = new_logical;
+        Ok(())
     }
 
     /// Get the current timestamp
-    fn get_time(&self) -> (u64, u64) {
-        (self.physical_time, self.logical_counter)
+    fn get_time(&self) -> Timestamp {
+        Timestamp {
+            physical: self.physical_time,
+            logical: self.logical_counter,
+            id: self.id,
+        }
     }
 }
 
@@ -59,47 +260,562 @@ fn current_millis() -> u64 {
         .as_millis() as u64
 }
 
+/// A simplified high-dynamic-range histogram: each value is bucketed by its
+/// own magnitude (`HIST_SUB_BUCKETS_PER_OCTAVE` buckets per doubling), so
+/// relative precision stays roughly constant whether values are in the
+/// microseconds or seconds range, without the unbounded memory a linear
+/// histogram would need to cover that span.
+struct Histogram {
+    sub_buckets_per_octave: u32,
+    counts: HashMap<u32, u64>,
+    total_count: u64,
+    max: u64,
+}
+
+impl Histogram {
+    fn new(sub_buckets_per_octave: u32) -> Self {
+        Histogram {
+            sub_buckets_per_octave,
+            counts: HashMap::new(),
+            total_count: 0,
+            max: 0,
+        }
+    }
+
+    fn bucket_index(&self, value: u64) -> u32 {
+        let value = value.max(1) as f64;
+        (value.log2() * self.sub_buckets_per_octave as f64).round() as u32
+    }
+
+    fn bucket_value(&self, index: u32) -> u64 {
+        2f64.powf(index as f64 / self.sub_buckets_per_octave as f64).round() as u64
+    }
+
+    fn record(&mut self, value: u64) {
+        let index = self.bucket_index(value);
+        *self.counts.entry(index).or_insert(0) += 1;
+        self.total_count += 1;
+        self.max = self.max.max(value);
+    }
+
+    /// Smallest recorded value whose bucket is at or above the `p`th
+    /// percentile (0.0..=100.0).
+    fn percentile(&self, p: f64) -> u64 {
+        if self.total_count == 0 {
+            return 0;
+        }
+        let target = ((p / 100.0) * self.total_count as f64).ceil().max(1.0) as u64;
+        let mut indices: Vec<u32> = self.counts.keys().copied().collect();
+        indices.sort_unstable();
+        let mut cumulative = 0u64;
+        for index in indices {
+            cumulative += self.counts[&index];
+            if cumulative >= target {
+                return self.bucket_value(index);
+            }
+        }
+        self.max
+    }
+
+    /// Convenience accessor for the (p50, p99, p99.9) triple.
+    fn percentiles(&self) -> (u64, u64, u64) {
+        (self.percentile(50.0), self.percentile(99.0), self.percentile(99.9))
+    }
+}
+
+/// Per-node latency metrics, both histograms measured in microseconds:
+/// delivery latency is the time between `send_message` and the matching
+/// `handle_message`; update duration is the time spent inside
+/// `HybridLogicalClock::update_with_timestamp`.
+struct Metrics {
+    delivery_latency_us: Histogram,
+    update_duration_us: Histogram,
+}
+
+impl Metrics {
+    fn new() -> Self {
+        Metrics {
+            delivery_latency_us: Histogram::new(HIST_SUB_BUCKETS_PER_OCTAVE),
+            update_duration_us: Histogram::new(HIST_SUB_BUCKETS_PER_OCTAVE),
+        }
+    }
+}
+
+/// Spawn a background thread that, every `interval_secs` seconds, appends a
+/// line to `path` recording the interval's start timestamp, its duration,
+/// `tag`, and the current delivery-latency and clock-update-duration
+/// histograms, so runs can be compared offline.
+fn spawn_metrics_logger(tag: String, metrics: Arc<Mutex<Metrics>>, interval_secs: u64, path: String) {
+    thread::spawn(move || loop {
+        thread::sleep(Duration::from_secs(interval_secs));
+        let start = current_millis();
+        let (delivery_p50, delivery_p99, delivery_p999, update_p50, update_p99, update_p999) = {
+            let metrics = metrics.lock().unwrap();
+            let (d50, d99, d999) = metrics.delivery_latency_us.percentiles();
+            let (u50, u99, u999) = metrics.update_duration_us.percentiles();
+            (d50, d99, d999, u50, u99, u999)
+        };
+        let line = format!(
+            "start={} interval_secs={} tag={} delivery_latency_us_p50={} delivery_latency_us_p99={} delivery_latency_us_p999={} update_duration_us_p50={} update_duration_us_p99={} update_duration_us_p999={}\n",
+            start, interval_secs, tag, delivery_p50, delivery_p99, delivery_p999, update_p50, update_p99, update_p999
+        );
+        match OpenOptions::new().create(true).append(true).open(&path) {
+            Ok(mut file) => {
+                if let Err(err) = file.write_all(line.as_bytes()) {
+                    warn!("failed to write metrics interval log to {}: {}", path, err);
+                }
+            }
+            Err(err) => warn!("failed to open metrics log {}: {}", path, err),
+        }
+    });
+}
+
+/// Per-node link characteristics used by `Network` to model bandwidth and
+/// latency instead of delivering messages instantaneously.
+#[derive(Clone, Copy, Debug)]
+struct LinkConfig {
+    /// Bytes per second this node's outgoing link can transmit.
+    capacity_bps: u64,
+    /// Fixed propagation delay, in milliseconds, added to every send.
+    base_latency_ms: u64,
+    /// Maximum additional random delay, in milliseconds, added per send.
+    jitter_ms: u64,
+    /// Probability (0.0..=1.0) that a message is dropped in flight.
+    drop_probability: f64,
+}
+
+impl Default for LinkConfig {
+    fn default() -> Self {
+        LinkConfig {
+            capacity_bps: 1_000_000,
+            base_latency_ms: 20,
+            jitter_ms: 10,
+            drop_probability: 0.0,
+        }
+    }
+}
+
+/// A message queued for delivery once `release_at` arrives. Ordered by
+/// `release_at` alone so `Network`'s pending queue can use it as a min-heap
+/// key via `Reverse`.
+struct ScheduledDelivery {
+    release_at: Instant,
+    message: Message,
+}
+
+impl PartialEq for ScheduledDelivery {
+    fn eq(&self, other: &Self) -> bool {
+        self.release_at == other.release_at
+    }
+}
+
+impl Eq for ScheduledDelivery {}
+
+impl PartialOrd for ScheduledDelivery {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for ScheduledDelivery {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.release_at.cmp(&other.release_at)
+    }
+}
+
+/// Sits between `Node`s and their inbound channels, modeling a
+/// bandwidth- and latency-limited network instead of instantaneous
+/// delivery: each send is scheduled for release after a capacity-derived
+/// transmission delay plus base latency and jitter, and can be dropped
+/// entirely, exercising `update_with_timestamp`'s drift logic under
+/// genuinely delayed and out-of-order delivery. Delivery is routed
+/// directly to the addressed node's own channel rather than broadcast to
+/// every node over one shared receiver.
+struct Network {
+    links: Mutex<HashMap<u32, LinkConfig>>,
+    busy_until: Mutex<HashMap<u32, Instant>>,
+    pending: Mutex<BinaryHeap<Reverse<ScheduledDelivery>>>,
+    /// Signaled whenever `send` pushes a new entry onto `pending`, so the
+    /// dispatcher sleeping for some earlier-scheduled release wakes up
+    /// immediately instead of oversleeping past a freshly-scheduled,
+    /// even-earlier delivery.
+    dispatch_cv: Condvar,
+    routes: Mutex<HashMap<u32, mpsc::Sender<Message>>>,
+}
+
+impl Network {
+    fn new() -> Arc<Self> {
+        let network = Arc::new(Network {
+            links: Mutex::new(HashMap::new()),
+            busy_until: Mutex::new(HashMap::new()),
+            pending: Mutex::new(BinaryHeap::new()),
+            dispatch_cv: Condvar::new(),
+            routes: Mutex::new(HashMap::new()),
+        });
+        Arc::clone(&network).spawn_dispatcher();
+        network
+    }
+
+    /// Configure the simulated link characteristics for `node_id`. Nodes
+    /// without an explicit link use `LinkConfig::default()`.
+    fn set_link(&self, node_id: u32, link: LinkConfig) {
+        self.links.lock().unwrap().insert(node_id, link);
+    }
+
+    /// Register `node_id`'s inbound channel in the routing table so the
+    /// network can deliver messages addressed to it directly.
+    fn register_route(&self, node_id: u32, sender: mpsc::Sender<Message>) {
+        self.routes.lock().unwrap().insert(node_id, sender);
+    }
+
+    /// Admit a `size_bytes` message from `sender_id` into the network,
+    /// scheduling its release after a transmission delay computed from the
+    /// sender's capacity and current in-flight load, plus base latency and
+    /// jitter. Returns `false` if the message was dropped instead of
+    /// scheduled.
+    fn send(&self, sender_id: u32, size_bytes: u64, message: Message) -> bool {
+        let link = self.links.lock().unwrap().get(&sender_id).copied().unwrap_or_default();
+
+        if link.drop_probability > 0.0 && rand::thread_rng().gen_range(0.0..1.0) < link.drop_probability {
+            return false;
+        }
+
+        let per_step_capacity_bytes = (link.capacity_bps as f64 / STEPS_PER_SECOND as f64).max(1.0);
+        let delay_steps = size_bytes as f64 / per_step_capacity_bytes;
+        let transmit_ms = (delay_steps * (1000.0 / STEPS_PER_SECOND as f64)).ceil() as u64;
+        let jitter_ms = if link.jitter_ms > 0 {
+            rand::thread_rng().gen_range(0..=link.jitter_ms)
+        } else {
+            0
+        };
+
+        let now = Instant::now();
+        let transmit_end = {
+            let mut busy_until = self.busy_until.lock().unwrap();
+            let transmit_start = busy_until.get(&sender_id).copied().unwrap_or(now).max(now);
+            let transmit_end = transmit_start + Duration::from_millis(transmit_ms);
+            busy_until.insert(sender_id, transmit_end);
+            transmit_end
+        };
+
+        let release_at = transmit_end + Duration::from_millis(link.base_latency_ms + jitter_ms);
+        self.pending
+            .lock()
+            .unwrap()
+            .push(Reverse(ScheduledDelivery { release_at, message }));
+        // Wake the dispatcher in case it's sleeping for a later release:
+        // this one may need to go out sooner.
+        self.dispatch_cv.notify_one();
+        true
+    }
+
+    /// Background thread that pops the earliest-scheduled message off the
+    /// min-heap once its release time has arrived and routes it directly
+    /// to its target node's channel, so delivery order can differ from
+    /// send order. Waits on `dispatch_cv` rather than a flat `sleep` so a
+    /// newly-pushed, earlier-release message (signaled by `send`) wakes it
+    /// immediately instead of waiting out whatever it was sleeping for.
+    fn spawn_dispatcher(self: Arc<Self>) {
+        thread::spawn(move || loop {
+            let mut pending = self.pending.lock().unwrap();
+            loop {
+                match pending.peek().map(|Reverse(scheduled)| scheduled.release_at) {
+                    Some(release_at) => {
+                        let now = Instant::now();
+                        if release_at <= now {
+                            break;
+                        }
+                        pending = self.dispatch_cv.wait_timeout(pending, release_at - now).unwrap().0;
+                    }
+                    None => {
+                        pending = self.dispatch_cv.wait(pending).unwrap();
+                    }
+                }
+            }
+            let due = pending.pop();
+            drop(pending);
+            if let Some(Reverse(scheduled)) = due {
+                let target = self.routes.lock().unwrap().get(&scheduled.message.target_id).cloned();
+                if let Some(target) = target {
+                    let _ = target.send(scheduled.message);
+                }
+            }
+        });
+    }
+}
+
+/// A locally-generated event, stamped with the originating node's HLC at
+/// creation time. Ordered by `timestamp` alone so it can be buffered in an
+/// `EventLog`'s min-heap and delivered in HLC order.
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct Event {
+    timestamp: Timestamp,
+    payload: String,
+}
+
+impl PartialOrd for Event {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Event {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.timestamp.cmp(&other.timestamp)
+    }
+}
+
+/// A replicated, totally-ordered event log built on top of the HLC.
+/// Incoming events are buffered in a priority queue keyed by their HLC
+/// timestamp, but only "delivered" once they're stable: every tracked
+/// peer has been heard from at a timestamp strictly past the event's,
+/// which — because each peer's HLC only moves forward — guarantees no
+/// earlier event from that peer can still arrive.
+///
+/// `members` should be every other node whose events must be accounted
+/// for; over anything less than a fully-connected topology, a peer's
+/// events may need to be relayed through an intermediary to be observed
+/// here, which this simple single-hop gossip model doesn't do.
+struct EventLog {
+    members: Vec<NodeId>,
+    highest_seen: HashMap<NodeId, Timestamp>,
+    buffered: BinaryHeap<Reverse<Event>>,
+}
+
+impl EventLog {
+    fn new(members: Vec<NodeId>) -> Self {
+        EventLog {
+            members,
+            highest_seen: HashMap::new(),
+            buffered: BinaryHeap::new(),
+        }
+    }
+
+    /// Record that we've heard from `peer` at (at least) `timestamp`.
+    fn record_heard_from(&mut self, peer: NodeId, timestamp: Timestamp) {
+        let highest = self.highest_seen.entry(peer).or_insert(timestamp);
+        if timestamp > *highest {
+            *highest = timestamp;
+        }
+    }
+
+    /// Buffer a gossiped (or locally-generated) event and record that
+    /// we've heard from its origin at (at least) its timestamp.
+    fn observe_event(&mut self, event: Event) {
+        self.record_heard_from(event.timestamp.id, event.timestamp);
+        self.buffered.push(Reverse(event));
+    }
+
+    /// The stability watermark: the minimum, across every tracked member,
+    /// of the highest timestamp we've heard from them. `None` until we've
+    /// heard from all of them at least once.
+    fn watermark(&self) -> Option<Timestamp> {
+        if self.members.is_empty() {
+            // No peers to wait on (e.g. an isolated node under
+            // `Topology::RandomSparse`): nothing can arrive to reorder our
+            // own buffered events ahead of anything else, so everything
+            // buffered is already stable. Without this, `members.iter().any`
+            // is vacuously false and we'd fall through to `.min()` over an
+            // empty iterator, returning `None` forever and stalling
+            // delivery for good.
+            return Some(Timestamp { physical: u64::MAX, logical: u64::MAX, id: NodeId(u32::MAX) });
+        }
+        if self.members.iter().any(|member| !self.highest_seen.contains_key(member)) {
+            return None;
+        }
+        self.members.iter().map(|member| self.highest_seen[member]).min()
+    }
+
+    /// Deliver every buffered event strictly below the stability
+    /// watermark, in sorted HLC order.
+    fn deliver_stable(&mut self) -> Vec<Event> {
+        let watermark = match self.watermark() {
+            Some(watermark) => watermark,
+            None => return Vec::new(),
+        };
+
+        let mut delivered = Vec::new();
+        while let Some(Reverse(event)) = self.buffered.peek() {
+            if event.timestamp < watermark {
+                if let Some(Reverse(event)) = self.buffered.pop() {
+                    delivered.push(event);
+                }
+            } else {
+                break;
+            }
+        }
+        delivered
+    }
+}
+
 /// Node struct to represent a distributed node
 struct Node {
     id: u32,
     clock: HybridLogicalClock,
-    sender: mpsc::Sender<Message>,
+    network: Arc<Network>,
+    /// Ids of the peers this node may send to, per the cluster's topology.
+    peers: Vec<u32>,
+    next_seq: u64,
+    metrics: Arc<Mutex<Metrics>>,
+    /// Most recently published clock reading, kept in sync with `clock` so
+    /// `Cluster` can read a node's state from outside its thread (e.g. for
+    /// test assertions after quiescence).
+    snapshot: Arc<Mutex<Timestamp>>,
+    event_log: EventLog,
 }
 
 impl Node {
-    fn new(id: u32, sender: mpsc::Sender<Message>) -> Self {
+    /// `peers` are the ids this node may send to; `inbound_peers` are the
+    /// ids it may receive gossip from. These coincide under a symmetric
+    /// topology but not under a directed one like `Topology::Ring`, so
+    /// `EventLog` — which needs to know who it hears from, not who it
+    /// sends to — is seeded from `inbound_peers`.
+    fn new(
+        id: u32,
+        network: Arc<Network>,
+        peers: Vec<u32>,
+        inbound_peers: Vec<u32>,
+        snapshot: Arc<Mutex<Timestamp>>,
+        max_drift_millis: u64,
+    ) -> Self {
+        let clock = HybridLogicalClock::with_max_drift(NodeId(id), max_drift_millis);
+        *snapshot.lock().unwrap() = clock.get_time();
+        let event_log = EventLog::new(inbound_peers.iter().map(|&peer| NodeId(peer)).collect());
         Node {
             id,
-            clock: HybridLogicalClock::new(),
-            sender,
+            clock,
+            network,
+            peers,
+            next_seq: 0,
+            metrics: Arc::new(Mutex::new(Metrics::new())),
+            snapshot,
+            event_log,
         }
     }
 
-    /// Simulate sending a message to another node
+    /// Simulate sending a message to another node through the network's
+    /// bandwidth/latency model.
     fn send_message(&mut self, target_id: u32) {
         self.clock.increment();
         let timestamp = self.clock.get_time();
+        self.publish_snapshot();
+        let seq = self.next_seq;
+        self.next_seq += 1;
         println!(
-            "Node {} sending message with HLC {:?} to Node {}",
-            self.id, timestamp, target_id
+            "Node {} sending message #{} with HLC {:?} (packed {}) to Node {}",
+            self.id, seq, timestamp, timestamp.to_packed(), target_id
         );
-        self.sender
-            .send(Message {
+        let message = Message {
+            sender_id: self.id,
+            target_id,
+            timestamp: timestamp.to_packed(),
+            seq,
+            sent_at: Instant::now(),
+            event: None,
+        };
+        // Approximate the on-wire size with the in-memory representation;
+        // good enough for a prototype that has no real serialization yet.
+        let size_bytes = std::mem::size_of::<Message>() as u64;
+        if !self.network.send(self.id, size_bytes, message) {
+            warn!("Node {} message #{} dropped in flight", self.id, seq);
+        }
+    }
+
+    /// Send to a uniformly random neighbor from this node's topology, or do
+    /// nothing if it has none.
+    fn send_to_random_peer(&mut self) {
+        if self.peers.is_empty() {
+            return;
+        }
+        let target_id = self.peers[rand::thread_rng().gen_range(0..self.peers.len())];
+        self.send_message(target_id);
+    }
+
+    /// Generate a new event stamped with this node's current HLC, record
+    /// it in the local log, and gossip it to every peer so the cluster can
+    /// eventually deliver it in a single, globally agreed order.
+    fn broadcast_event(&mut self, payload: String) {
+        self.clock.increment();
+        let timestamp = self.clock.get_time();
+        self.publish_snapshot();
+        let event = Event { timestamp, payload };
+        self.event_log.observe_event(event.clone());
+
+        for target_id in self.peers.clone() {
+            let seq = self.next_seq;
+            self.next_seq += 1;
+            let message = Message {
                 sender_id: self.id,
                 target_id,
-                timestamp: self.clock.clone(),
-            })
-            .expect("Failed to send message");
+                timestamp: timestamp.to_packed(),
+                seq,
+                sent_at: Instant::now(),
+                event: Some(event.clone()),
+            };
+            let size_bytes = std::mem::size_of::<Message>() as u64;
+            if !self.network.send(self.id, size_bytes, message) {
+                warn!("Node {} event broadcast #{} to Node {} dropped in flight", self.id, seq, target_id);
+            }
+        }
     }
 
     /// Handle an incoming message
     fn handle_message(&mut self, message: Message) {
+        let remote_timestamp = Timestamp::from_packed(message.timestamp, NodeId(message.sender_id));
         println!(
-            "Node {} received message from Node {} with HLC {:?}",
-            self.id, message.sender_id, message.timestamp
+            "Node {} received message #{} from Node {} with HLC {:?}",
+            self.id, message.seq, message.sender_id, remote_timestamp
         );
-        self.clock.update(&message.timestamp);
-        println!("Node {} updated HLC to {:?}", self.id, self.clock.get_time());
+
+        let delivery_latency = message.sent_at.elapsed();
+        let update_started_at = Instant::now();
+        let result = self.clock.update_with_timestamp(&remote_timestamp);
+        let update_duration = update_started_at.elapsed();
+        {
+            let mut metrics = self.metrics.lock().unwrap();
+            metrics.delivery_latency_us.record(delivery_latency.as_micros() as u64);
+            metrics.update_duration_us.record(update_duration.as_micros() as u64);
+        }
+
+        self.event_log.record_heard_from(NodeId(message.sender_id), remote_timestamp);
+        if let Some(event) = message.event.clone() {
+            self.event_log.observe_event(event);
+        }
+
+        match result {
+            Ok(()) => {
+                self.publish_snapshot();
+                println!("Node {} updated HLC to {:?}", self.id, self.clock.get_time());
+            }
+            Err(err) => {
+                warn!(
+                    "Node {} rejected HLC update from Node {}: {}",
+                    self.id, message.sender_id, err
+                );
+            }
+        }
+    }
+
+    /// Deliver every event in this node's log that is now stable, in
+    /// sorted HLC order.
+    fn deliver_stable(&mut self) -> Vec<Event> {
+        self.event_log.deliver_stable()
+    }
+
+    /// Current (p50, p99, p99.9) delivery-latency percentiles, in
+    /// microseconds, observed by this node so far.
+    fn latency_percentiles(&self) -> (u64, u64, u64) {
+        self.metrics.lock().unwrap().delivery_latency_us.percentiles()
+    }
+
+    /// Current (p50, p99, p99.9) `update_with_timestamp` duration
+    /// percentiles, in microseconds, observed by this node so far.
+    fn update_duration_percentiles(&self) -> (u64, u64, u64) {
+        self.metrics.lock().unwrap().update_duration_us.percentiles()
+    }
+
+    fn publish_snapshot(&self) {
+        *self.snapshot.lock().unwrap() = self.clock.get_time();
     }
 }
 
@@ -108,55 +824,527 @@ impl Node {
 struct Message {
     sender_id: u32,
     target_id: u32,
-    timestamp: HybridLogicalClock,
+    timestamp: PackedClock,
+    /// Monotonically increasing per-sender sequence number, used to
+    /// correlate a delivered message with the `send_message` call that
+    /// produced it.
+    seq: u64,
+    /// When this message was sent, used to compute delivery latency.
+    sent_at: Instant,
+    /// A gossiped event, if this message is carrying one alongside the
+    /// regular HLC heartbeat.
+    event: Option<Event>,
 }
 
-/// Simulate the network communication between nodes
-fn simulate_network() {
-    // Channels for message passing
-    let (tx, rx) = mpsc::channel();
-    let rx = Arc::new(Mutex::new(rx));
-
-    // Create two nodes
-    let mut node1 = Node::new(1, tx.clone());
-    let mut node2 = Node::new(2, tx.clone());
+/// Communication topology used to derive each node's peer list.
+#[derive(Clone, Debug)]
+enum Topology {
+    /// Every node may send to every other node.
+    FullyConnected,
+    /// Node `i` may only send to node `i + 1` (wrapping around).
+    Ring,
+    /// Each unordered pair of nodes is connected independently with
+    /// probability `edge_probability`, using a seeded RNG so the resulting
+    /// graph is reproducible.
+    RandomSparse { seed: u64, edge_probability: f64 },
+}
 
-    // Thread to simulate Node 1 behavior
-    let rx1 = Arc::clone(&rx);
-    thread::spawn(move || loop {
-        let delay = rand::thread_rng().gen_range(1..=3);
-        thread::sleep(Duration::from_secs(delay));
-        node1.send_message(2);
+impl Topology {
+    /// Compute the adjacency list (ids a node may send to) for nodes
+    /// numbered `1..=n`.
+    fn adjacency(&self, n: u32) -> HashMap<u32, Vec<u32>> {
+        match *self {
+            Topology::FullyConnected => (1..=n)
+                .map(|id| (id, (1..=n).filter(|&peer| peer != id).collect()))
+                .collect(),
+            Topology::Ring => (1..=n)
+                .map(|id| {
+                    let next = if id == n { 1 } else { id + 1 };
+                    (id, vec![next])
+                })
+                .collect(),
+            Topology::RandomSparse { seed, edge_probability } => {
+                let mut rng = StdRng::seed_from_u64(seed);
+                let mut adjacency: HashMap<u32, Vec<u32>> = (1..=n).map(|id| (id, Vec::new())).collect();
+                for a in 1..=n {
+                    for b in (a + 1)..=n {
+                        if rng.gen_range(0.0..1.0) < edge_probability {
+                            adjacency.get_mut(&a).unwrap().push(b);
+                            adjacency.get_mut(&b).unwrap().push(a);
+                        }
+                    }
+                }
+                adjacency
+            }
+        }
+    }
 
-        if let Ok(message) = rx1.lock().unwrap().recv() {
-            if message.target_id == 1 {
-                node1.handle_message(message);
+    /// Compute the *inbound* adjacency list (ids that may send to a node)
+    /// for nodes numbered `1..=n`: the reverse of `adjacency`. For
+    /// symmetric topologies (`FullyConnected`, `RandomSparse`) this is
+    /// identical to `adjacency`, but for a directed topology like `Ring`
+    /// it differs — node `i`'s outbound peer is `i + 1`, but its inbound
+    /// sender is `i - 1`. `EventLog::members` needs this set, not the
+    /// outbound one, since the watermark can only be based on peers this
+    /// node actually hears from.
+    fn inbound_adjacency(&self, n: u32) -> HashMap<u32, Vec<u32>> {
+        let mut inbound: HashMap<u32, Vec<u32>> = (1..=n).map(|id| (id, Vec::new())).collect();
+        for (sender, targets) in self.adjacency(n) {
+            for target in targets {
+                inbound.get_mut(&target).unwrap().push(sender);
             }
         }
-    });
+        inbound
+    }
+}
 
-    // Thread to simulate Node 2 behavior
-    let rx2 = Arc::clone(&rx);
-    thread::spawn(move || loop {
-        let delay = rand::thread_rng().gen_range(1..=3);
-        thread::sleep(Duration::from_secs(delay));
-        node2.send_message(1);
+/// A configurable N-node cluster wired through a `Network` according to a
+/// communication `Topology`. Replaces the old hardcoded two-node
+/// simulation: each node gets its own inbound channel registered in the
+/// network's routing table, and sends to a random neighbor on a random
+/// interval until `run_for`'s duration elapses.
+struct Cluster {
+    snapshots: HashMap<u32, Arc<Mutex<Timestamp>>>,
+    stop: Arc<AtomicBool>,
+    threads: Vec<thread::JoinHandle<()>>,
+}
 
-        if let Ok(message) = rx2.lock().unwrap().recv() {
-            if message.target_id == 2 {
-                node2.handle_message(message);
-            }
+impl Cluster {
+    /// Spawn `n` nodes (ids `1..=n`) wired according to `topology` and
+    /// start them sending immediately. Each node's clock rejects remote
+    /// timestamps more than `max_drift_millis` ahead of its own notion of
+    /// "now"; see `HybridLogicalClock::with_max_drift`.
+    fn new(n: u32, topology: Topology, max_drift_millis: u64) -> Self {
+        let adjacency = topology.adjacency(n);
+        let inbound_adjacency = topology.inbound_adjacency(n);
+        let network = Network::new();
+        let stop = Arc::new(AtomicBool::new(false));
+        let mut snapshots = HashMap::new();
+        let mut threads = Vec::new();
+
+        for id in 1..=n {
+            network.set_link(id, LinkConfig::default());
+            let (tx, rx) = mpsc::channel();
+            network.register_route(id, tx);
+
+            let snapshot = Arc::new(Mutex::new(Timestamp {
+                physical: 0,
+                logical: 0,
+                id: NodeId(id),
+            }));
+            snapshots.insert(id, Arc::clone(&snapshot));
+
+            let peers = adjacency.get(&id).cloned().unwrap_or_default();
+            let inbound_peers = inbound_adjacency.get(&id).cloned().unwrap_or_default();
+            let mut node = Node::new(id, Arc::clone(&network), peers, inbound_peers, snapshot, max_drift_millis);
+            spawn_metrics_logger(
+                format!("node{}", id),
+                Arc::clone(&node.metrics),
+                METRICS_LOG_INTERVAL_SECS,
+                format!("metrics_node{}.log", id),
+            );
+
+            let stop = Arc::clone(&stop);
+            threads.push(thread::spawn(move || {
+                let mut next_send_at = Instant::now();
+                let mut next_event_seq = 0u64;
+                while !stop.load(AtomicOrdering::Relaxed) {
+                    if Instant::now() >= next_send_at {
+                        if rand::thread_rng().gen_bool(0.5) {
+                            node.broadcast_event(format!("node-{}-event-{}", id, next_event_seq));
+                            next_event_seq += 1;
+                        } else {
+                            node.send_to_random_peer();
+                        }
+                        let delay_secs = rand::thread_rng().gen_range(1..=3);
+                        next_send_at = Instant::now() + Duration::from_secs(delay_secs);
+                    }
+                    match rx.recv_timeout(Duration::from_millis(100)) {
+                        Ok(message) => node.handle_message(message),
+                        Err(mpsc::RecvTimeoutError::Timeout) => {}
+                        Err(mpsc::RecvTimeoutError::Disconnected) => break,
+                    }
+                    for event in node.deliver_stable() {
+                        info!("Node {} delivered stable event {:?}", node.id, event);
+                    }
+                }
+            }));
         }
-    });
 
-    // Keep the main thread alive
-    loop {
-        thread::sleep(Duration::from_secs(10));
+        Cluster { snapshots, stop, threads }
+    }
+
+    /// Let the cluster run for `duration`, then signal every node thread
+    /// to stop and wait for them to exit.
+    fn run_for(self, duration: Duration) -> HashMap<u32, Timestamp> {
+        thread::sleep(duration);
+        self.stop.store(true, AtomicOrdering::Relaxed);
+        for handle in self.threads {
+            let _ = handle.join();
+        }
+        self.snapshots
+            .iter()
+            .map(|(&id, snapshot)| (id, *snapshot.lock().unwrap()))
+            .collect()
+    }
+
+    /// Each node's most recently published clock reading. Useful for
+    /// assertions after quiescence, e.g. that all nodes' physical times
+    /// are within one tick of each other.
+    fn clock_snapshots(&self) -> HashMap<u32, Timestamp> {
+        self.snapshots
+            .iter()
+            .map(|(&id, snapshot)| (id, *snapshot.lock().unwrap()))
+            .collect()
     }
 }
 
 fn main() {
         env_logger::init();
     info!("Starting the HLC distributed system...");
-    simulate_network();
+    let cluster = Cluster::new(3, Topology::FullyConnected, DEFAULT_MAX_DRIFT_MILLIS);
+    let snapshots = cluster.run_for(Duration::from_secs(30));
+    let mut ids: Vec<_> = snapshots.keys().copied().collect();
+    ids.sort_unstable();
+    for id in ids {
+        info!("Node {} final HLC: {:?}", id, snapshots[&id]);
+    }
+}
+
+#[cfg(test)]
+mod cluster_tests {
+    use super::*;
+
+    /// After a cluster has been gossiping for a while, every node's HLC
+    /// physical time should be within a tick of every other's: they all
+    /// derive from the same wall clock, and `update_with_timestamp` only
+    /// ever pulls a node's physical time *forward* to match its peers, so
+    /// sustained exchange should leave them clustered tightly together.
+    #[test]
+    fn quiescent_cluster_physical_times_converge() {
+        let cluster = Cluster::new(3, Topology::FullyConnected, DEFAULT_MAX_DRIFT_MILLIS);
+        thread::sleep(Duration::from_millis(500));
+        // Exercised here as well as via `run_for`'s return value, since
+        // both are meant to expose the same per-node state for assertions.
+        let _mid_run = cluster.clock_snapshots();
+
+        let snapshots = cluster.run_for(Duration::from_millis(500));
+        assert_eq!(snapshots.len(), 3);
+
+        let min_physical = snapshots.values().map(|ts| ts.physical).min().unwrap();
+        let max_physical = snapshots.values().map(|ts| ts.physical).max().unwrap();
+        assert!(
+            max_physical - min_physical <= DEFAULT_MAX_DRIFT_MILLIS,
+            "node physical times should converge within the drift bound, got spread of {}ms",
+            max_physical - min_physical
+        );
+    }
+
+    /// Two nodes each generate an event, gossip it to the other, then
+    /// exchange one more heartbeat so each one's stability watermark
+    /// advances past both events' timestamps. At that point
+    /// `deliver_stable` on either node must return both events in the
+    /// same HLC order: the whole point of the watermark is that once an
+    /// event is stable, every node agrees on where it falls relative to
+    /// every other stable event.
+    #[test]
+    fn interleaved_events_deliver_in_identical_order() {
+        let network = Network::new();
+        network.set_link(1, LinkConfig::default());
+        network.set_link(2, LinkConfig::default());
+
+        let (tx1, rx1) = mpsc::channel();
+        let (tx2, rx2) = mpsc::channel();
+        network.register_route(1, tx1);
+        network.register_route(2, tx2);
+
+        let snapshot1 = Arc::new(Mutex::new(Timestamp { physical: 0, logical: 0, id: NodeId(1) }));
+        let snapshot2 = Arc::new(Mutex::new(Timestamp { physical: 0, logical: 0, id: NodeId(2) }));
+        let mut node1 = Node::new(1, Arc::clone(&network), vec![2], vec![2], snapshot1, DEFAULT_MAX_DRIFT_MILLIS);
+        let mut node2 = Node::new(2, Arc::clone(&network), vec![1], vec![1], snapshot2, DEFAULT_MAX_DRIFT_MILLIS);
+
+        let recv = |rx: &mpsc::Receiver<Message>| rx.recv_timeout(Duration::from_millis(200)).expect("message should arrive");
+
+        // Round 1: each node broadcasts its own event and delivers the
+        // other's.
+        node1.broadcast_event("node-1-event".to_string());
+        node2.broadcast_event("node-2-event".to_string());
+        node1.handle_message(recv(&rx1));
+        node2.handle_message(recv(&rx2));
+
+        // Round 2: a plain heartbeat from each node so the other's
+        // watermark advances strictly past both events' timestamps.
+        node1.send_message(2);
+        node2.send_message(1);
+        node1.handle_message(recv(&rx1));
+        node2.handle_message(recv(&rx2));
+
+        let delivered1 = node1.deliver_stable();
+        let delivered2 = node2.deliver_stable();
+        assert_eq!(delivered1.len(), 2, "both events should now be stable on node 1");
+        assert_eq!(delivered1, delivered2, "both nodes must deliver stable events in the same order");
+    }
+}
+
+#[cfg(test)]
+mod histogram_tests {
+    use super::*;
+
+    /// Recording a set of exact powers of two avoids any rounding in
+    /// `bucket_index`/`bucket_value` (their bucketing is exact on
+    /// power-of-two boundaries), so the expected percentiles can be
+    /// computed by hand and checked precisely.
+    #[test]
+    fn percentiles_match_hand_computed_values() {
+        let mut histogram = Histogram::new(HIST_SUB_BUCKETS_PER_OCTAVE);
+        for value in [1u64, 2, 4, 8, 16, 32, 64, 128, 256, 512, 1024] {
+            histogram.record(value);
+        }
+        assert_eq!(histogram.percentiles(), (32, 1024, 1024));
+    }
+}
+
+#[cfg(test)]
+mod node_metrics_tests {
+    use super::*;
+
+    /// After a message is exchanged and handled, both of `Node`'s
+    /// percentile accessors should reflect the recorded sample rather
+    /// than the all-zero empty-histogram default.
+    #[test]
+    fn node_exposes_latency_and_update_duration_percentiles_after_traffic() {
+        let network = Network::new();
+        network.set_link(1, LinkConfig::default());
+        network.set_link(2, LinkConfig::default());
+        let (tx1, _rx1) = mpsc::channel();
+        let (tx2, rx2) = mpsc::channel();
+        network.register_route(1, tx1);
+        network.register_route(2, tx2);
+
+        let snapshot1 = Arc::new(Mutex::new(Timestamp { physical: 0, logical: 0, id: NodeId(1) }));
+        let snapshot2 = Arc::new(Mutex::new(Timestamp { physical: 0, logical: 0, id: NodeId(2) }));
+        let mut node1 = Node::new(1, Arc::clone(&network), vec![2], vec![2], snapshot1, DEFAULT_MAX_DRIFT_MILLIS);
+        let mut node2 = Node::new(2, Arc::clone(&network), vec![1], vec![1], snapshot2, DEFAULT_MAX_DRIFT_MILLIS);
+
+        node1.send_message(2);
+        let message = rx2.recv_timeout(Duration::from_millis(200)).expect("message should arrive");
+        node2.handle_message(message);
+
+        let (latency_p50, latency_p99, latency_p999) = node2.latency_percentiles();
+        assert!(latency_p50 <= latency_p99 && latency_p99 <= latency_p999);
+
+        let (update_p50, update_p99, update_p999) = node2.update_duration_percentiles();
+        assert!(update_p50 <= update_p99 && update_p99 <= update_p999);
+    }
+}
+
+#[cfg(test)]
+mod network_tests {
+    use super::*;
+
+    fn dummy_message(sender_id: u32, target_id: u32, seq: u64) -> Message {
+        Message {
+            sender_id,
+            target_id,
+            timestamp: PackedClock::from_u64(0),
+            seq,
+            sent_at: Instant::now(),
+            event: None,
+        }
+    }
+
+    /// A message sent with a much shorter configured latency must still
+    /// be delivered before an earlier-sent, longer-latency message: the
+    /// dispatcher must wake for the freshly-scheduled earlier release
+    /// instead of oversleeping whatever it was already waiting for.
+    #[test]
+    fn dispatcher_delivers_in_release_order_not_send_order() {
+        let network = Network::new();
+        network.set_link(1, LinkConfig { capacity_bps: 1_000_000, base_latency_ms: 300, jitter_ms: 0, drop_probability: 0.0 });
+        network.set_link(2, LinkConfig { capacity_bps: 1_000_000, base_latency_ms: 10, jitter_ms: 0, drop_probability: 0.0 });
+
+        let (tx, rx) = mpsc::channel();
+        network.register_route(9, tx);
+
+        assert!(network.send(1, 8, dummy_message(1, 9, 1)));
+        assert!(network.send(2, 8, dummy_message(2, 9, 2)));
+
+        let first = rx.recv_timeout(Duration::from_millis(500)).expect("first delivery should arrive");
+        assert_eq!(first.sender_id, 2, "the shorter-latency message should be delivered first");
+
+        let second = rx.recv_timeout(Duration::from_millis(500)).expect("second delivery should arrive");
+        assert_eq!(second.sender_id, 1);
+    }
+
+    #[test]
+    fn send_with_drop_probability_one_never_delivers() {
+        let network = Network::new();
+        network.set_link(1, LinkConfig { capacity_bps: 1_000_000, base_latency_ms: 1, jitter_ms: 0, drop_probability: 1.0 });
+        let (tx, rx) = mpsc::channel();
+        network.register_route(9, tx);
+
+        let admitted = network.send(1, 8, dummy_message(1, 9, 1));
+        assert!(!admitted, "send should report the message as dropped rather than scheduled");
+        assert!(rx.recv_timeout(Duration::from_millis(200)).is_err(), "a dropped message must never be delivered");
+    }
+}
+
+#[cfg(test)]
+mod topology_tests {
+    use super::*;
+
+    #[test]
+    fn ring_adjacency_is_directed_single_hop() {
+        let adjacency = Topology::Ring.adjacency(4);
+        assert_eq!(adjacency[&1], vec![2]);
+        assert_eq!(adjacency[&2], vec![3]);
+        assert_eq!(adjacency[&3], vec![4]);
+        assert_eq!(adjacency[&4], vec![1]);
+    }
+
+    /// Node `i`'s inbound sender is `i - 1` (wrapping), the reverse of its
+    /// outbound peer `i + 1` — this is exactly what `EventLog::members`
+    /// needs to be seeded from for a directed topology like `Ring`.
+    #[test]
+    fn ring_inbound_adjacency_is_the_reverse_of_adjacency() {
+        let inbound = Topology::Ring.inbound_adjacency(4);
+        assert_eq!(inbound[&1], vec![4]);
+        assert_eq!(inbound[&2], vec![1]);
+        assert_eq!(inbound[&3], vec![2]);
+        assert_eq!(inbound[&4], vec![3]);
+    }
+
+    #[test]
+    fn random_sparse_adjacency_is_symmetric_and_deterministic() {
+        let topology = Topology::RandomSparse { seed: 42, edge_probability: 0.5 };
+        let first = topology.adjacency(6);
+        let second = topology.adjacency(6);
+        assert_eq!(first, second, "the same seed must produce the same graph every time");
+
+        for (&node, peers) in &first {
+            for &peer in peers {
+                assert!(
+                    first[&peer].contains(&node),
+                    "edge {}->{} should be mirrored as {}->{}",
+                    node, peer, peer, node
+                );
+            }
+        }
+    }
+
+    /// A zero `edge_probability` must isolate every node — the scenario
+    /// `EventLog::watermark`'s empty-`members` case exists to handle.
+    #[test]
+    fn random_sparse_with_zero_edge_probability_isolates_every_node() {
+        let adjacency = Topology::RandomSparse { seed: 7, edge_probability: 0.0 }.adjacency(5);
+        assert!(adjacency.values().all(|peers| peers.is_empty()));
+    }
+}
+
+#[cfg(test)]
+mod event_log_tests {
+    use super::*;
+
+    /// An isolated node (no inbound peers to wait on) must deliver its own
+    /// buffered events immediately rather than stalling forever: `members`
+    /// being empty means there's nothing that could still arrive to
+    /// reorder ahead of what's already buffered.
+    #[test]
+    fn isolated_node_delivers_immediately() {
+        let mut log = EventLog::new(Vec::new());
+        let event = Event {
+            timestamp: Timestamp { physical: 1, logical: 0, id: NodeId(1) },
+            payload: "solo".to_string(),
+        };
+        log.observe_event(event.clone());
+
+        let delivered = log.deliver_stable();
+        assert_eq!(delivered, vec![event]);
+    }
+}
+
+#[cfg(test)]
+mod packed_clock_tests {
+    use super::*;
+
+    #[test]
+    fn packed_clock_round_trips_through_timestamp() {
+        let original = Timestamp { physical: 123_456_789, logical: 42, id: NodeId(7) };
+        let packed = original.to_packed();
+        let restored = Timestamp::from_packed(packed, original.id);
+        assert_eq!(restored, original);
+    }
+
+    #[test]
+    fn packed_clock_round_trips_through_u64_and_display() {
+        let packed = PackedClock::from_u64(0xDEAD_BEEF);
+        assert_eq!(PackedClock::from_u64(packed.as_u64()), packed);
+
+        let parsed: PackedClock = packed.to_string().parse().expect("valid packed clock should parse");
+        assert_eq!(parsed, packed);
+    }
+
+    #[test]
+    fn packed_clock_from_str_rejects_garbage() {
+        assert!("not-a-number".parse::<PackedClock>().is_err());
+    }
+}
+
+#[cfg(test)]
+mod clock_tests {
+    use super::*;
+
+    /// A remote timestamp whose physical time is far beyond the clock's
+    /// configured `max_drift_millis` must be rejected with
+    /// `ClockError::DriftTooLarge`, and must leave the local clock
+    /// untouched so a single wildly-skewed peer can't drag it forward.
+    #[test]
+    fn update_with_timestamp_rejects_excessive_drift() {
+        let mut clock = HybridLogicalClock::with_max_drift(NodeId(1), 100);
+        let before = clock.get_time();
+
+        let remote = Timestamp {
+            physical: before.physical + 10_000,
+            logical: 0,
+            id: NodeId(2),
+        };
+        let result = clock.update_with_timestamp(&remote);
+
+        assert_eq!(result, Err(ClockError::DriftTooLarge));
+        assert_eq!(clock.get_time(), before, "a rejected update must not advance the local clock");
+    }
+
+    /// `increment`'s saturation path: when the logical counter has
+    /// exhausted its bit width within the current millisecond, it must
+    /// spin until physical time ticks forward and reset to zero, rather
+    /// than silently wrapping back to an already-used value.
+    #[test]
+    fn increment_spins_past_logical_counter_saturation() {
+        let mut clock = HybridLogicalClock::new(NodeId(1));
+        let baseline = clock.physical_time;
+        clock.logical_counter = MAX_LOGICAL;
+
+        clock.increment();
+
+        assert!(clock.physical_time > baseline, "physical time should have ticked forward");
+        assert_eq!(clock.logical_counter, 0);
+    }
+
+    /// `update_with_timestamp`'s saturation path: when merging with a
+    /// remote timestamp would overflow the logical counter, it must spin
+    /// until physical time ticks forward and reset to zero instead of
+    /// wrapping.
+    #[test]
+    fn update_with_timestamp_spins_past_logical_counter_saturation() {
+        let mut clock = HybridLogicalClock::new(NodeId(1));
+        let baseline = clock.physical_time;
+        clock.logical_counter = MAX_LOGICAL;
+
+        let remote = Timestamp { physical: baseline, logical: MAX_LOGICAL, id: NodeId(2) };
+        let result = clock.update_with_timestamp(&remote);
+
+        assert_eq!(result, Ok(()));
+        assert!(clock.physical_time > baseline, "physical time should have ticked forward");
+        assert_eq!(clock.logical_counter, 0);
+    }
 }